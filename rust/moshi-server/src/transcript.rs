@@ -1,14 +1,26 @@
-use crate::vad::{EnergyVad, VadConfig};
+use std::collections::VecDeque;
+
+use crate::vad::{EnergyVad, SampleFormat, VadConfig, VadEvent};
 
 #[derive(Debug, Clone)]
 pub struct TrackerConfig {
     pub vad: VadConfig,
     pub finalize_after_s: f64,
+    /// Retain ingested audio so finalized segments can carry their own PCM slice.
+    pub retain_audio: bool,
+    /// Upper bound, in seconds, on audio kept for lookback once it precedes the active
+    /// segment's start; older samples are pruned to keep memory use bounded on long streams.
+    pub max_retained_s: f64,
 }
 
 impl Default for TrackerConfig {
     fn default() -> Self {
-        Self { vad: VadConfig::default(), finalize_after_s: 0.8 }
+        Self {
+            vad: VadConfig::default(),
+            finalize_after_s: 0.8,
+            retain_audio: false,
+            max_retained_s: 30.0,
+        }
     }
 }
 
@@ -18,6 +30,18 @@ pub struct TranscriptUpdate {
     pub start_time: f64,
     pub stop_time: Option<f64>,
     pub is_final: bool,
+    /// The segment's PCM, present only on final updates when `TrackerConfig::retain_audio`
+    /// is set and the range hasn't already been pruned from the retained buffer.
+    pub audio: Option<Vec<f32>>,
+}
+
+/// Why a requested audio range couldn't be returned from the retained buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioRangeError {
+    /// `start_time` is negative or `stop_time` precedes it.
+    InvalidRange,
+    /// The requested range has already been pruned to stay within `max_retained_s`.
+    Pruned,
 }
 
 #[derive(Debug, Clone)]
@@ -27,7 +51,7 @@ struct EmittedState {
     is_final: bool,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct TranscriptTracker {
     vad: EnergyVad,
     finalize_after_s: f64,
@@ -35,10 +59,20 @@ pub struct TranscriptTracker {
     start_time: Option<f64>,
     last_stop_time: Option<f64>,
     last_emitted: Option<EmittedState>,
+    retain_audio: bool,
+    max_retained_s: f64,
+    sample_rate: usize,
+    session_audio: VecDeque<f32>,
+    /// Absolute count of samples ever pushed into `session_audio`.
+    processed_samples: u64,
+    /// Absolute count of samples dropped from the front of `session_audio`; subtract this
+    /// from a sample index to get its position in the buffer.
+    deleted_samples: u64,
 }
 
 impl TranscriptTracker {
     pub fn new(cfg: TrackerConfig) -> Self {
+        let sample_rate = cfg.vad.sample_rate;
         Self {
             vad: EnergyVad::new(cfg.vad),
             finalize_after_s: cfg.finalize_after_s,
@@ -46,6 +80,12 @@ impl TranscriptTracker {
             start_time: None,
             last_stop_time: None,
             last_emitted: None,
+            retain_audio: cfg.retain_audio,
+            max_retained_s: cfg.max_retained_s,
+            sample_rate,
+            session_audio: VecDeque::new(),
+            processed_samples: 0,
+            deleted_samples: 0,
         }
     }
 
@@ -55,11 +95,103 @@ impl TranscriptTracker {
         self.last_stop_time = None;
         self.last_emitted = None;
         self.vad.reset();
+        self.session_audio.clear();
+        self.processed_samples = 0;
+        self.deleted_samples = 0;
     }
 
     pub fn ingest_audio(&mut self, pcm: &[f32]) -> Vec<TranscriptUpdate> {
+        // Append before running the VAD so a `SpeechEnded` in this very chunk can still
+        // `finalize` with the complete audio, but prune only afterwards: `prune_audio`'s
+        // "don't prune past the active segment start" guard needs this chunk's `start_time`
+        // update (from a possible `SpeechStart`), not last call's.
+        self.append_audio(pcm);
+        let events = self.vad.process(pcm);
+        let updates = self.handle_vad_events(events);
+        self.prune_audio();
+        updates
+    }
+
+    /// Ingests raw, possibly non-`f32` PCM at an arbitrary `in_sample_rate`; see
+    /// `EnergyVad::ingest_raw` for the supported formats and resampling behavior.
+    pub fn ingest_raw(
+        &mut self,
+        bytes: &[u8],
+        format: SampleFormat,
+        in_sample_rate: usize,
+    ) -> Vec<TranscriptUpdate> {
+        let pcm = self.vad.decode_and_resample_raw(bytes, format, in_sample_rate);
+        self.append_audio(&pcm);
+        let events = self.vad.process(&pcm);
+        let updates = self.handle_vad_events(events);
+        self.prune_audio();
+        updates
+    }
+
+    /// Returns the retained PCM covering `[start_time, stop_time)`, or an error if the
+    /// range is invalid or has already been pruned from the buffer.
+    pub fn audio_range(&self, start_time: f64, stop_time: f64) -> Result<Vec<f32>, AudioRangeError> {
+        if start_time < 0.0 || stop_time < start_time {
+            return Err(AudioRangeError::InvalidRange);
+        }
+        let start_idx = (start_time * self.sample_rate as f64).round() as u64;
+        let stop_idx = (stop_time * self.sample_rate as f64).round() as u64;
+        if start_idx < self.deleted_samples {
+            return Err(AudioRangeError::Pruned);
+        }
+        let buf_start = (start_idx - self.deleted_samples) as usize;
+        // Clamp against samples actually ingested so far; this equals the buffer's current
+        // length (`processed_samples - deleted_samples == session_audio.len()`).
+        let retained = self.processed_samples.saturating_sub(self.deleted_samples) as usize;
+        let buf_stop = (stop_idx - self.deleted_samples).min(retained as u64) as usize;
+        Ok(self
+            .session_audio
+            .iter()
+            .skip(buf_start)
+            .take(buf_stop.saturating_sub(buf_start))
+            .copied()
+            .collect())
+    }
+
+    /// Appends `pcm` to the retained buffer without pruning; callers prune separately once
+    /// this chunk's VAD events (and thus `start_time`) have been applied.
+    fn append_audio(&mut self, pcm: &[f32]) {
+        if !self.retain_audio || pcm.is_empty() {
+            return;
+        }
+        self.session_audio.extend(pcm.iter().copied());
+        self.processed_samples += pcm.len() as u64;
+    }
+
+    fn prune_audio(&mut self) {
+        let max_samples = (self.max_retained_s * self.sample_rate as f64).round() as u64;
+        let segment_start_sample =
+            self.start_time.map(|t| (t * self.sample_rate as f64).round() as u64);
+        while self.processed_samples - self.deleted_samples > max_samples {
+            if let Some(start) = segment_start_sample {
+                if self.deleted_samples >= start {
+                    break;
+                }
+            }
+            self.session_audio.pop_front();
+            self.deleted_samples += 1;
+        }
+    }
+
+    fn handle_vad_events(&mut self, events: Vec<VadEvent>) -> Vec<TranscriptUpdate> {
         let mut updates = Vec::new();
-        self.vad.process(pcm);
+        for event in events {
+            match event {
+                VadEvent::SpeechStart { start_time } => {
+                    self.start_time.get_or_insert(start_time);
+                }
+                VadEvent::SpeechEnded { end_time } => {
+                    if let Some(update) = self.finalize(Some(end_time)) {
+                        updates.push(update);
+                    }
+                }
+            }
+        }
         if self.should_finalize_by_timer() {
             if let Some(update) = self.finalize(Some(self.vad.last_voice_time())) {
                 updates.push(update);
@@ -118,8 +250,13 @@ impl TranscriptTracker {
             return None;
         }
         let stop_time = self.last_stop_time;
-        let update =
-            TranscriptUpdate { text: text.clone(), start_time, stop_time, is_final: false };
+        let update = TranscriptUpdate {
+            text: text.clone(),
+            start_time,
+            stop_time,
+            is_final: false,
+            audio: None,
+        };
         if self.should_emit(&update) {
             self.last_emitted = Some(EmittedState { text, stop_time, is_final: false });
             return Some(update);
@@ -142,7 +279,12 @@ impl TranscriptTracker {
                 stop_time = Some(start_time);
             }
         }
-        let update = TranscriptUpdate { text: text.clone(), start_time, stop_time, is_final: true };
+        let audio = match (self.retain_audio, stop_time) {
+            (true, Some(stop)) => self.audio_range(start_time, stop).ok(),
+            _ => None,
+        };
+        let update =
+            TranscriptUpdate { text: text.clone(), start_time, stop_time, is_final: true, audio };
         self.reset_segment_state();
         Some(update)
     }
@@ -180,3 +322,70 @@ impl TranscriptTracker {
         silence >= self.finalize_after_s
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vad::VadMode;
+
+    /// 1 kHz / 10-sample frames with round thresholds and a 50-sample `max_retained_s`, so
+    /// pruning boundaries can be reasoned about in exact sample counts.
+    fn test_cfg() -> TrackerConfig {
+        TrackerConfig {
+            vad: VadConfig {
+                sample_rate: 1000,
+                frame_length: 10,
+                onset_threshold: 0.5,
+                offset_threshold: 0.2,
+                min_silence_duration: 0.03,
+                min_speech_duration_s: 0.03,
+                speech_pad_s: 0.0,
+                denoise: false,
+                denoise_prob_threshold: 0.5,
+                mode: VadMode::Energy,
+                loudness_offset_lu: 9.0,
+                loudness_release_lu: 6.0,
+            },
+            finalize_after_s: 0.8,
+            retain_audio: true,
+            max_retained_s: 0.05,
+        }
+    }
+
+    fn loud(len: usize) -> Vec<f32> {
+        vec![1.0; len]
+    }
+
+    fn silent(len: usize) -> Vec<f32> {
+        vec![0.0; len]
+    }
+
+    #[test]
+    fn prune_drops_old_samples_once_max_retained_s_is_exceeded() {
+        let mut tracker = TranscriptTracker::new(test_cfg());
+        // 200 samples of silence never triggers speech, so nothing guards pruning: the
+        // buffer should settle at exactly max_retained_s (50 samples).
+        for _ in 0..20 {
+            tracker.ingest_audio(&silent(10));
+        }
+        assert_eq!(tracker.audio_range(0.0, 0.05), Err(AudioRangeError::Pruned));
+        let retained = tracker.audio_range(0.15, 0.2).unwrap();
+        assert_eq!(retained.len(), 50);
+    }
+
+    #[test]
+    fn prune_does_not_cross_the_active_segment_start() {
+        let mut tracker = TranscriptTracker::new(test_cfg());
+        // Leading silence, then enough sustained loud audio to confirm speech at t=0.03.
+        tracker.ingest_audio(&silent(20));
+        tracker.ingest_audio(&loud(30));
+        // Far more audio than max_retained_s (50 samples) accumulates while still in speech.
+        tracker.ingest_audio(&loud(100));
+
+        // The pre-segment silence has been pruned away...
+        assert_eq!(tracker.audio_range(0.0, 0.02), Err(AudioRangeError::Pruned));
+        // ...but the entire active segment survives, even though it's 3x max_retained_s.
+        let retained = tracker.audio_range(0.03, 0.15).unwrap();
+        assert_eq!(retained.len(), 120);
+    }
+}
@@ -1,11 +1,41 @@
 use std::collections::VecDeque;
 
+use nnnoiseless::DenoiseState;
+
+/// Sample rate the RNNoise-style denoiser operates at; unrelated to `VadConfig::sample_rate`.
+const DENOISE_SAMPLE_RATE: usize = 48_000;
+
 #[derive(Debug, Clone)]
 pub struct VadConfig {
     pub sample_rate: usize,
     pub frame_length: usize,
-    pub energy_threshold: f32,
+    /// Energy level (high threshold) above which silence transitions to speech.
+    pub onset_threshold: f32,
+    /// Energy level (low threshold) below which speech transitions towards silence.
+    pub offset_threshold: f32,
     pub min_silence_duration: f64,
+    /// Minimum duration a speech run must sustain before it is confirmed; shorter runs
+    /// are treated as noise spikes and discarded without emitting any event.
+    pub min_speech_duration_s: f64,
+    /// Pre/post roll applied to reported segment boundaries: `SpeechStart` is backdated
+    /// and `SpeechEnded` is padded forward by this many seconds.
+    pub speech_pad_s: f64,
+    /// Run incoming audio through an RNNoise-style denoiser before energy detection.
+    /// Only the detector sees the denoised signal; reported timestamps still track the
+    /// original stream.
+    pub denoise: bool,
+    /// Voice-activity probability (from the denoiser) above which a frame counts as
+    /// speech even if its energy falls short of the onset/offset threshold.
+    pub denoise_prob_threshold: f32,
+    /// Which detector drives the onset/offset decision; see [`VadMode`].
+    pub mode: VadMode,
+    /// In [`VadMode::Loudness`], how many LU above the adaptive noise floor a frame's
+    /// loudness must be to trigger onset (silence -> speech).
+    pub loudness_offset_lu: f32,
+    /// In [`VadMode::Loudness`], how many LU above the adaptive noise floor a frame's
+    /// loudness must stay at to hold speech (speech -> silence uses this lower margin,
+    /// mirroring `offset_threshold`'s hysteresis gap below `onset_threshold`).
+    pub loudness_release_lu: f32,
 }
 
 impl Default for VadConfig {
@@ -13,45 +43,398 @@ impl Default for VadConfig {
         Self {
             sample_rate: 24000,
             frame_length: 480,
-            energy_threshold: 7.5e-4,
+            onset_threshold: 7.5e-4,
+            offset_threshold: 4e-4,
             min_silence_duration: 0.6,
+            min_speech_duration_s: 0.1,
+            speech_pad_s: 0.1,
+            denoise: false,
+            denoise_prob_threshold: 0.5,
+            mode: VadMode::Energy,
+            loudness_offset_lu: 9.0,
+            loudness_release_lu: 6.0,
         }
     }
 }
 
+/// Selects which [`VadDetector`] impl `EnergyVad` drives its onset/offset decisions from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VadMode {
+    /// Raw mean-square energy compared against `onset_threshold`/`offset_threshold`.
+    Energy,
+    /// EBU R128 K-weighted loudness compared against an adaptive noise floor, so the same
+    /// relative threshold works across input gains without per-deployment tuning.
+    Loudness,
+}
+
+/// Linear resampler with state carried across calls so streaming chunks that don't land
+/// on frame boundaries resample continuously, without clicks at the seams.
+#[derive(Debug, Clone)]
+pub(crate) struct LinearResampler {
+    in_rate: usize,
+    out_rate: usize,
+    pos: f64,
+    prev_sample: f32,
+    have_prev: bool,
+}
+
+impl LinearResampler {
+    pub(crate) fn new(in_rate: usize, out_rate: usize) -> Self {
+        Self { in_rate, out_rate, pos: 0.0, prev_sample: 0.0, have_prev: false }
+    }
+
+    pub(crate) fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        if input.is_empty() || self.in_rate == self.out_rate {
+            return input.to_vec();
+        }
+        let ratio = self.in_rate as f64 / self.out_rate as f64;
+        let mut extended = Vec::with_capacity(input.len() + 1);
+        extended.push(if self.have_prev { self.prev_sample } else { input[0] });
+        extended.extend_from_slice(input);
+        let mut out = Vec::new();
+        while self.pos + 1.0 < extended.len() as f64 {
+            let idx = self.pos.floor() as usize;
+            let frac = (self.pos - idx as f64) as f32;
+            out.push(extended[idx] + (extended[idx + 1] - extended[idx]) * frac);
+            self.pos += ratio;
+        }
+        self.pos -= (extended.len() - 1) as f64;
+        self.prev_sample = *input.last().expect("checked non-empty above");
+        self.have_prev = true;
+        out
+    }
+}
+
+/// Denoised audio plus, for each contiguous run of samples that came from the same
+/// 480-sample/48 kHz denoiser frame, the voice-activity probability the denoiser reported
+/// for it. `prob_segments` sample counts always sum to `pcm.len()`.
+struct DenoisedChunk {
+    pcm: Vec<f32>,
+    prob_segments: Vec<(f32, usize)>,
+}
+
+/// Denoises audio at the VAD's native sample rate by resampling it to the 48 kHz frames
+/// the RNNoise-style `DenoiseState` expects, running it, and resampling the result back.
+struct Denoiser {
+    state: Box<DenoiseState<'static>>,
+    resample_in: LinearResampler,
+    resample_out: LinearResampler,
+    frame_buffer: Vec<f32>,
+}
+
+impl std::fmt::Debug for Denoiser {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Denoiser")
+            .field("resample_in", &self.resample_in)
+            .field("resample_out", &self.resample_out)
+            .field("frame_buffer_len", &self.frame_buffer.len())
+            .finish()
+    }
+}
+
+impl Denoiser {
+    fn new(native_rate: usize) -> Self {
+        Self {
+            state: DenoiseState::new(),
+            resample_in: LinearResampler::new(native_rate, DENOISE_SAMPLE_RATE),
+            resample_out: LinearResampler::new(DENOISE_SAMPLE_RATE, native_rate),
+            frame_buffer: Vec::with_capacity(DenoiseState::FRAME_SIZE),
+        }
+    }
+
+    fn reset(&mut self, native_rate: usize) {
+        *self = Self::new(native_rate);
+    }
+
+    /// Denoises `pcm`, resampling each 480-sample/48 kHz denoiser frame back to the native
+    /// rate as soon as it's produced, so its voice-activity probability can be attributed
+    /// to exactly the native-rate samples it covers (rather than one scalar per call).
+    fn process(&mut self, pcm: &[f32]) -> DenoisedChunk {
+        self.frame_buffer.extend(self.resample_in.process(pcm));
+        let mut out = DenoisedChunk { pcm: Vec::new(), prob_segments: Vec::new() };
+        let mut out_frame = vec![0.0f32; DenoiseState::FRAME_SIZE];
+        while self.frame_buffer.len() >= DenoiseState::FRAME_SIZE {
+            let frame: Vec<f32> = self.frame_buffer.drain(..DenoiseState::FRAME_SIZE).collect();
+            let prob = self.state.process_frame(&mut out_frame, &frame);
+            let resampled = self.resample_out.process(&out_frame);
+            if !resampled.is_empty() {
+                out.prob_segments.push((prob, resampled.len()));
+            }
+            out.pcm.extend_from_slice(&resampled);
+        }
+        out
+    }
+}
+
+/// Raw PCM sample encoding accepted by [`EnergyVad::ingest_raw`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleFormat {
+    /// Unsigned 8-bit, midpoint at 128.
+    U8,
+    /// Signed 16-bit, little-endian.
+    S16,
+    /// Signed 24-bit sample held in the low-order 24 bits of a little-endian 32-bit word.
+    S24In32,
+    /// Little-endian 32-bit float already normalized to `[-1, 1]`.
+    F32,
+}
+
+impl SampleFormat {
+    fn bytes_per_sample(self) -> usize {
+        match self {
+            SampleFormat::U8 => 1,
+            SampleFormat::S16 => 2,
+            SampleFormat::S24In32 => 4,
+            SampleFormat::F32 => 4,
+        }
+    }
+
+    fn decode(self, raw: &[u8]) -> f32 {
+        match self {
+            SampleFormat::U8 => (raw[0] as f32 - 128.0) / 128.0,
+            SampleFormat::S16 => i16::from_le_bytes([raw[0], raw[1]]) as f32 / 32768.0,
+            SampleFormat::S24In32 => {
+                let word = i32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]);
+                // Sign-extend from 24 bits regardless of whether the high byte was
+                // already zero-padded or sign-padded by the caller.
+                let sign_extended = (word << 8) >> 8;
+                sign_extended as f32 / 8_388_608.0
+            }
+            SampleFormat::F32 => f32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]),
+        }
+    }
+}
+
+/// Computes a per-frame voice-activity measurement and the thresholds to compare it
+/// against; `EnergyVad` keeps its onset/offset/hysteresis state machine unchanged and
+/// simply swaps out what "measurement" and "threshold" mean.
+trait VadDetector: std::fmt::Debug {
+    /// Measures `frame` (native sample rate), updating any internal adaptive state.
+    /// `in_silence` tells the detector whether the VAD is currently in the `Silence`
+    /// state (as opposed to `Pending` or `Speech`), so e.g. a noise floor can be adapted
+    /// only on frames measured in `Silence`. It is passed before the frame's onset decision,
+    /// so the frame that triggers an onset is still measured with `in_silence == true`.
+    fn measure(&mut self, frame: &[f32], in_silence: bool) -> f32;
+    fn onset_threshold(&self, cfg: &VadConfig) -> f32;
+    fn offset_threshold(&self, cfg: &VadConfig) -> f32;
+}
+
+/// Today's behavior: raw mean-square energy against the configured fixed thresholds.
+#[derive(Debug, Default)]
+struct EnergyDetector;
+
+impl VadDetector for EnergyDetector {
+    fn measure(&mut self, frame: &[f32], _in_silence: bool) -> f32 {
+        let sum: f32 = frame.iter().map(|sample| sample * sample).sum();
+        sum / frame.len().max(1) as f32
+    }
+
+    fn onset_threshold(&self, cfg: &VadConfig) -> f32 {
+        cfg.onset_threshold
+    }
+
+    fn offset_threshold(&self, cfg: &VadConfig) -> f32 {
+        cfg.offset_threshold
+    }
+}
+
+/// Direct Form II Transposed biquad, used for the K-weighting prefilter.
+#[derive(Debug, Clone, Copy)]
+struct Biquad {
+    b0: f64,
+    b1: f64,
+    b2: f64,
+    a1: f64,
+    a2: f64,
+    z1: f64,
+    z2: f64,
+}
+
+impl Biquad {
+    fn new(b0: f64, b1: f64, b2: f64, a1: f64, a2: f64) -> Self {
+        Self { b0, b1, b2, a1, a2, z1: 0.0, z2: 0.0 }
+    }
+
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b0 * x + self.z1;
+        self.z1 = self.b1 * x - self.a1 * y + self.z2;
+        self.z2 = self.b2 * x - self.a2 * y;
+        y
+    }
+}
+
+/// EBU R128 / ITU-R BS.1770 K-weighting prefilter: a high-shelf stage followed by a
+/// high-pass stage, both derived from the standard analog prototype coefficients via the
+/// bilinear transform at `sample_rate`.
+#[derive(Debug, Clone, Copy)]
+struct KWeightingFilter {
+    shelf: Biquad,
+    high_pass: Biquad,
+}
+
+impl KWeightingFilter {
+    fn new(sample_rate: f64) -> Self {
+        let shelf = {
+            let f0 = 1_681.974_450_955_533_2;
+            let g = 3.999_843_853_97_f64;
+            let q = 0.707_175_236_955_419_3;
+            let k = (std::f64::consts::PI * f0 / sample_rate).tan();
+            let vh = 10f64.powf(g / 20.0);
+            let vb = vh.powf(0.499_666_774_154_541_6);
+            let a0 = 1.0 + k / q + k * k;
+            Biquad::new(
+                (vh + vb * k / q + k * k) / a0,
+                2.0 * (k * k - vh) / a0,
+                (vh - vb * k / q + k * k) / a0,
+                2.0 * (k * k - 1.0) / a0,
+                (1.0 - k / q + k * k) / a0,
+            )
+        };
+        let high_pass = {
+            let f0 = 38.135_470_876_139_82;
+            let q = 0.500_327_037_323_877_3;
+            let k = (std::f64::consts::PI * f0 / sample_rate).tan();
+            let a0 = 1.0 + k / q + k * k;
+            Biquad::new(1.0, -2.0, 1.0, 2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0)
+        };
+        Self { shelf, high_pass }
+    }
+
+    fn process(&mut self, x: f32) -> f64 {
+        let shelved = self.shelf.process(x as f64);
+        self.high_pass.process(shelved)
+    }
+}
+
+/// Loudness-normalized detector: K-weighted LUFS compared against a slowly-adapting noise
+/// floor, so the same relative margin works regardless of input gain.
+#[derive(Debug, Clone, Copy)]
+struct LoudnessDetector {
+    filter: KWeightingFilter,
+    noise_floor_lufs: f64,
+    floor_initialized: bool,
+}
+
+impl LoudnessDetector {
+    /// Smoothing factor for the noise-floor EMA; adapted only while in silence.
+    const FLOOR_EMA_ALPHA: f64 = 0.05;
+
+    fn new(sample_rate: usize) -> Self {
+        Self {
+            filter: KWeightingFilter::new(sample_rate as f64),
+            noise_floor_lufs: -70.0,
+            floor_initialized: false,
+        }
+    }
+}
+
+impl VadDetector for LoudnessDetector {
+    fn measure(&mut self, frame: &[f32], in_silence: bool) -> f32 {
+        let mean_power =
+            frame.iter().map(|&s| self.filter.process(s).powi(2)).sum::<f64>() / frame.len().max(1) as f64;
+        let loudness_lufs = -0.691 + 10.0 * mean_power.max(1e-12).log10();
+        // Only frames measured while the VAD is in `Silence` feed the floor; frames measured
+        // in `Pending` or `Speech` do not. The frame that triggers an onset is measured while
+        // still in `Silence`, so it does feed the floor.
+        if in_silence {
+            if self.floor_initialized {
+                self.noise_floor_lufs +=
+                    Self::FLOOR_EMA_ALPHA * (loudness_lufs - self.noise_floor_lufs);
+            } else {
+                self.noise_floor_lufs = loudness_lufs;
+                self.floor_initialized = true;
+            }
+        }
+        loudness_lufs as f32
+    }
+
+    fn onset_threshold(&self, cfg: &VadConfig) -> f32 {
+        self.noise_floor_lufs as f32 + cfg.loudness_offset_lu
+    }
+
+    fn offset_threshold(&self, cfg: &VadConfig) -> f32 {
+        self.noise_floor_lufs as f32 + cfg.loudness_release_lu
+    }
+}
+
+fn build_detector(cfg: &VadConfig) -> Box<dyn VadDetector> {
+    match cfg.mode {
+        VadMode::Energy => Box::new(EnergyDetector),
+        VadMode::Loudness => Box::new(LoudnessDetector::new(cfg.sample_rate)),
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum VadEvent {
+    SpeechStart { start_time: f64 },
     SpeechEnded { end_time: f64 },
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum VadState {
+    Silence,
+    // Speech onset detected but not yet confirmed: `elapsed` tracks how long the run has
+    // sustained and `start_time` is the already-padded timestamp to report once confirmed.
+    Pending { start_time: f64, elapsed: f64 },
+    Speech,
+}
+
+#[derive(Debug)]
 pub struct EnergyVad {
     cfg: VadConfig,
     buffer: VecDeque<f32>,
     current_time: f64,
-    in_speech: bool,
+    state: VadState,
     silence_accumulator: f64,
     last_voice_time: f64,
+    denoiser: Option<Denoiser>,
+    last_voice_prob: f32,
+    /// Denoiser voice-activity probabilities not yet consumed by a detection frame, each
+    /// tagged with how many buffered native-rate samples (front of `buffer`) it covers.
+    prob_queue: VecDeque<(f32, usize)>,
+    detector: Box<dyn VadDetector>,
+    /// Trailing bytes left over from an `ingest_raw` call that didn't end on a sample
+    /// boundary; prepended to the next call's input.
+    byte_carry: Vec<u8>,
+    /// Resampler from the last seen `ingest_raw` input rate to `cfg.sample_rate`, rebuilt
+    /// whenever that input rate changes.
+    input_resampler: Option<(usize, LinearResampler)>,
 }
 
 impl EnergyVad {
     pub fn new(cfg: VadConfig) -> Self {
+        let denoiser = cfg.denoise.then(|| Denoiser::new(cfg.sample_rate));
+        let detector = build_detector(&cfg);
         Self {
             cfg,
             buffer: VecDeque::new(),
             current_time: 0.0,
-            in_speech: false,
+            state: VadState::Silence,
             silence_accumulator: 0.0,
             last_voice_time: 0.0,
+            denoiser,
+            last_voice_prob: 0.0,
+            prob_queue: VecDeque::new(),
+            detector,
+            byte_carry: Vec::new(),
+            input_resampler: None,
         }
     }
 
     pub fn reset(&mut self) {
         self.buffer.clear();
         self.current_time = 0.0;
-        self.in_speech = false;
+        self.state = VadState::Silence;
         self.silence_accumulator = 0.0;
         self.last_voice_time = 0.0;
+        if let Some(denoiser) = self.denoiser.as_mut() {
+            denoiser.reset(self.cfg.sample_rate);
+        }
+        self.last_voice_prob = 0.0;
+        self.prob_queue.clear();
+        self.detector = build_detector(&self.cfg);
+        self.byte_carry.clear();
+        self.input_resampler = None;
     }
 
     pub fn current_time(&self) -> f64 {
@@ -63,14 +446,57 @@ impl EnergyVad {
     }
 
     pub fn in_speech(&self) -> bool {
-        self.in_speech
+        matches!(self.state, VadState::Speech)
+    }
+
+    /// Voice-activity probability reported by the denoiser for the most recently processed
+    /// frame; always `0.0` when `VadConfig::denoise` is disabled.
+    pub fn last_voice_prob(&self) -> f32 {
+        self.last_voice_prob
+    }
+
+    /// Whether a frame with this detector `measurement` counts as voiced against
+    /// `threshold`, optionally also gating on the denoiser's voice-activity probability.
+    fn is_voiced(&self, measurement: f32, threshold: f32) -> bool {
+        measurement >= threshold
+            || (self.cfg.denoise && self.last_voice_prob >= self.cfg.denoise_prob_threshold)
+    }
+
+    /// Pops the voice-activity probability covering the next `frame_len` buffered
+    /// samples, weight-averaging across denoiser-frame boundaries that fall inside the
+    /// detection frame. Returns `0.0` once the queue runs dry (e.g. denoising disabled).
+    fn pop_frame_voice_prob(&mut self, frame_len: usize) -> f32 {
+        let mut remaining = frame_len;
+        let mut weighted_sum = 0.0f64;
+        while remaining > 0 {
+            let Some((prob, count)) = self.prob_queue.front_mut() else { break };
+            let take = remaining.min(*count);
+            weighted_sum += *prob as f64 * take as f64;
+            *count -= take;
+            remaining -= take;
+            if *count == 0 {
+                self.prob_queue.pop_front();
+            }
+        }
+        let covered = frame_len - remaining;
+        if covered == 0 {
+            0.0
+        } else {
+            (weighted_sum / covered as f64) as f32
+        }
     }
 
     pub fn process(&mut self, pcm: &[f32]) -> Vec<VadEvent> {
         if pcm.is_empty() {
             return vec![];
         }
-        self.buffer.extend(pcm.iter().copied());
+        if let Some(denoiser) = self.denoiser.as_mut() {
+            let chunk = denoiser.process(pcm);
+            self.prob_queue.extend(chunk.prob_segments);
+            self.buffer.extend(chunk.pcm);
+        } else {
+            self.buffer.extend(pcm.iter().copied());
+        }
         let mut events = Vec::new();
         let frame_len = self.cfg.frame_length;
         if frame_len == 0 || self.cfg.sample_rate == 0 {
@@ -78,28 +504,319 @@ impl EnergyVad {
         }
         let frame_duration = frame_len as f64 / self.cfg.sample_rate as f64;
         while self.buffer.len() >= frame_len {
-            let mut energy = 0.0f32;
-            for _ in 0..frame_len {
-                if let Some(sample) = self.buffer.pop_front() {
-                    energy += sample * sample;
-                }
-            }
-            energy /= frame_len as f32;
+            let frame: Vec<f32> = self.buffer.drain(..frame_len).collect();
+            self.last_voice_prob = self.pop_frame_voice_prob(frame_len);
+            let in_silence = matches!(self.state, VadState::Silence);
+            let measurement = self.detector.measure(&frame, in_silence);
+            let onset_threshold = self.detector.onset_threshold(&self.cfg);
+            let offset_threshold = self.detector.offset_threshold(&self.cfg);
             let next_time = self.current_time + frame_duration;
-            if energy >= self.cfg.energy_threshold {
-                self.in_speech = true;
-                self.silence_accumulator = 0.0;
-                self.last_voice_time = next_time;
-            } else if self.in_speech {
-                self.silence_accumulator += frame_duration;
-                if self.silence_accumulator >= self.cfg.min_silence_duration {
-                    self.in_speech = false;
-                    self.silence_accumulator = 0.0;
-                    events.push(VadEvent::SpeechEnded { end_time: self.last_voice_time });
+            match self.state {
+                VadState::Silence => {
+                    if self.is_voiced(measurement, onset_threshold) {
+                        let start_time = (next_time - self.cfg.speech_pad_s).max(0.0);
+                        self.state = VadState::Pending { start_time, elapsed: frame_duration };
+                    }
+                }
+                VadState::Pending { start_time, elapsed } => {
+                    if self.is_voiced(measurement, offset_threshold) {
+                        let elapsed = elapsed + frame_duration;
+                        if elapsed >= self.cfg.min_speech_duration_s {
+                            self.state = VadState::Speech;
+                            self.silence_accumulator = 0.0;
+                            // Only a confirmed run counts as voice activity: bumping this on
+                            // every onset attempt would let a discarded noise spike corrupt
+                            // the finalize-by-timer fallback timestamp.
+                            self.last_voice_time = next_time;
+                            events.push(VadEvent::SpeechStart { start_time });
+                        } else {
+                            self.state = VadState::Pending { start_time, elapsed };
+                        }
+                    } else {
+                        // The run died out before being confirmed: a noise spike, not speech.
+                        self.state = VadState::Silence;
+                    }
+                }
+                VadState::Speech => {
+                    if self.is_voiced(measurement, offset_threshold) {
+                        self.silence_accumulator = 0.0;
+                        self.last_voice_time = next_time;
+                    } else {
+                        self.silence_accumulator += frame_duration;
+                        if self.silence_accumulator >= self.cfg.min_silence_duration {
+                            self.state = VadState::Silence;
+                            self.silence_accumulator = 0.0;
+                            let end_time = self.last_voice_time + self.cfg.speech_pad_s;
+                            events.push(VadEvent::SpeechEnded { end_time });
+                        }
+                    }
                 }
             }
             self.current_time = next_time;
         }
         events
     }
+
+    /// Ingests raw bytes in `format` at `in_sample_rate`, converting to normalized `f32`
+    /// and resampling to `cfg.sample_rate` before running the usual `process` pipeline.
+    /// `current_time`/`last_voice_time` stay denominated in real seconds, so reported
+    /// timestamps match the caller's original audio timeline regardless of `in_sample_rate`.
+    pub fn ingest_raw(
+        &mut self,
+        bytes: &[u8],
+        format: SampleFormat,
+        in_sample_rate: usize,
+    ) -> Vec<VadEvent> {
+        let resampled = self.decode_and_resample_raw(bytes, format, in_sample_rate);
+        self.process(&resampled)
+    }
+
+    /// Converts and resamples raw bytes to `cfg.sample_rate` without running them through
+    /// the detector; callers that also need the decoded samples (e.g. for audio retention)
+    /// can use this directly and feed the result to `process` themselves.
+    pub fn decode_and_resample_raw(
+        &mut self,
+        bytes: &[u8],
+        format: SampleFormat,
+        in_sample_rate: usize,
+    ) -> Vec<f32> {
+        let step = format.bytes_per_sample();
+        self.byte_carry.extend_from_slice(bytes);
+        let usable = self.byte_carry.len() - (self.byte_carry.len() % step);
+        let samples: Vec<f32> =
+            self.byte_carry[..usable].chunks_exact(step).map(|raw| format.decode(raw)).collect();
+        self.byte_carry.drain(..usable);
+
+        let needs_new_resampler =
+            !matches!(&self.input_resampler, Some((rate, _)) if *rate == in_sample_rate);
+        if needs_new_resampler {
+            self.input_resampler =
+                Some((in_sample_rate, LinearResampler::new(in_sample_rate, self.cfg.sample_rate)));
+        }
+        self.input_resampler.as_mut().expect("just initialized above").1.process(&samples)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 100 Hz frame rate (10 samples @ 1 kHz) with round thresholds, so hysteresis timing
+    /// can be reasoned about in exact frame counts instead of the default config's units.
+    fn test_cfg() -> VadConfig {
+        VadConfig {
+            sample_rate: 1000,
+            frame_length: 10,
+            onset_threshold: 0.5,
+            offset_threshold: 0.2,
+            min_silence_duration: 0.03,
+            min_speech_duration_s: 0.03,
+            speech_pad_s: 0.0,
+            denoise: false,
+            denoise_prob_threshold: 0.5,
+            mode: VadMode::Energy,
+            loudness_offset_lu: 9.0,
+            loudness_release_lu: 6.0,
+        }
+    }
+
+    fn loud_frame(len: usize) -> Vec<f32> {
+        vec![1.0; len]
+    }
+
+    fn silent_frame(len: usize) -> Vec<f32> {
+        vec![0.0; len]
+    }
+
+    #[test]
+    fn confirms_speech_only_after_min_speech_duration() {
+        let mut vad = EnergyVad::new(test_cfg());
+        // Two loud frames (20ms) fall short of the 30ms min_speech_duration_s: still Pending.
+        let events = vad.process(&loud_frame(20));
+        assert!(events.is_empty());
+        assert!(!vad.in_speech());
+        // The third loud frame crosses min_speech_duration_s: onset confirmed.
+        let events = vad.process(&loud_frame(10));
+        assert_eq!(events.len(), 1);
+        assert!(matches!(events[0], VadEvent::SpeechStart { .. }));
+        assert!(vad.in_speech());
+    }
+
+    #[test]
+    fn discards_noise_spike_shorter_than_min_speech_duration() {
+        let mut vad = EnergyVad::new(test_cfg());
+        let events = vad.process(&loud_frame(10));
+        assert!(events.is_empty());
+        // The run dies out before confirmation: treated as a spike, no event at all.
+        let events = vad.process(&silent_frame(10));
+        assert!(events.is_empty());
+        assert!(!vad.in_speech());
+    }
+
+    #[test]
+    fn frame_voice_prob_weights_across_denoiser_segment_boundaries() {
+        let mut vad = EnergyVad::new(test_cfg());
+        vad.prob_queue.push_back((0.2, 4));
+        vad.prob_queue.push_back((0.8, 6));
+        // A 10-sample detection frame spanning a 4-sample 0.2 segment and a 6-sample 0.8
+        // segment should report their sample-weighted average, not just the most recent one.
+        let prob = vad.pop_frame_voice_prob(10);
+        let expected = (0.2 * 4.0 + 0.8 * 6.0) / 10.0;
+        assert!((prob - expected).abs() < 1e-6);
+    }
+
+    #[test]
+    fn frame_voice_prob_drains_in_order_and_runs_dry() {
+        let mut vad = EnergyVad::new(test_cfg());
+        vad.prob_queue.push_back((0.5, 25));
+        // A single denoiser segment spanning multiple detection frames: each frame sees the
+        // same probability until the segment is fully consumed.
+        assert!((vad.pop_frame_voice_prob(10) - 0.5).abs() < 1e-6);
+        assert!((vad.pop_frame_voice_prob(10) - 0.5).abs() < 1e-6);
+        assert!((vad.pop_frame_voice_prob(10) - 0.5).abs() < 1e-6);
+        // The queue only had 25 samples' worth; a further frame has nothing left to report.
+        assert_eq!(vad.pop_frame_voice_prob(10), 0.0);
+    }
+
+    #[test]
+    fn discarded_noise_spike_does_not_advance_last_voice_time() {
+        let mut vad = EnergyVad::new(test_cfg());
+        vad.process(&silent_frame(20));
+        // Onset attempt that dies out before confirmation: a discarded noise spike.
+        vad.process(&loud_frame(10));
+        vad.process(&silent_frame(10));
+        assert!(!vad.in_speech());
+        // last_voice_time must stay at its initial value: the spike was never confirmed as
+        // speech, so it must not corrupt the finalize-by-timer fallback timestamp.
+        assert_eq!(vad.last_voice_time(), 0.0);
+    }
+
+    #[test]
+    fn ends_speech_only_after_min_silence_duration() {
+        let mut vad = EnergyVad::new(test_cfg());
+        vad.process(&loud_frame(30));
+        assert!(vad.in_speech());
+        // Two silent frames (20ms) fall short of the 30ms min_silence_duration: still Speech.
+        let events = vad.process(&silent_frame(20));
+        assert!(events.is_empty());
+        assert!(vad.in_speech());
+        // The third silent frame crosses min_silence_duration: offset confirmed.
+        let events = vad.process(&silent_frame(10));
+        assert_eq!(events.len(), 1);
+        match events[0] {
+            VadEvent::SpeechEnded { end_time } => assert!((end_time - 0.03).abs() < 1e-9),
+            other => panic!("expected SpeechEnded, got {other:?}"),
+        }
+        assert!(!vad.in_speech());
+    }
+
+    #[test]
+    fn resampler_output_is_identical_regardless_of_chunking() {
+        let input: Vec<f32> = (0..100).map(|i| i as f32).collect();
+
+        let mut single = LinearResampler::new(4, 3);
+        let whole = single.process(&input);
+
+        // Feed the same signal through in small, unevenly-sized pieces: the resampler's
+        // carried-over position and previous sample should make the seams invisible.
+        let mut chunked = LinearResampler::new(4, 3);
+        let mut pieces = Vec::new();
+        for chunk in input.chunks(7) {
+            pieces.extend(chunked.process(chunk));
+        }
+
+        assert_eq!(whole.len(), pieces.len());
+        for (a, b) in whole.iter().zip(pieces.iter()) {
+            assert!((a - b).abs() < 1e-4, "{a} != {b}");
+        }
+    }
+
+    #[test]
+    fn resampler_handles_one_sample_at_a_time() {
+        let input: Vec<f32> = (0..40).map(|i| i as f32).collect();
+
+        let mut single = LinearResampler::new(3, 4);
+        let whole = single.process(&input);
+
+        let mut chunked = LinearResampler::new(3, 4);
+        let mut pieces = Vec::new();
+        for sample in &input {
+            pieces.extend(chunked.process(std::slice::from_ref(sample)));
+        }
+
+        assert_eq!(whole.len(), pieces.len());
+        for (a, b) in whole.iter().zip(pieces.iter()) {
+            assert!((a - b).abs() < 1e-4, "{a} != {b}");
+        }
+    }
+
+    /// A sine tone rather than a constant value, so the K-weighting high-pass stage (which
+    /// rejects DC) doesn't drag a fixed-amplitude signal's measured loudness toward zero.
+    fn sine_tone(amplitude: f32, sample_rate: f32, n: usize, phase: &mut f32) -> Vec<f32> {
+        (0..n)
+            .map(|_| {
+                let sample = amplitude * phase.sin();
+                *phase += 2.0 * std::f32::consts::PI * 1000.0 / sample_rate;
+                sample
+            })
+            .collect()
+    }
+
+    #[test]
+    fn loudness_floor_adapts_only_during_confirmed_silence() {
+        let sample_rate = 24_000.0;
+        let mut detector = LoudnessDetector::new(sample_rate as usize);
+        let mut phase = 0.0f32;
+
+        // Let the floor settle on repeated quiet, confirmed-silence frames.
+        for _ in 0..100 {
+            let quiet = sine_tone(0.001, sample_rate, 240, &mut phase);
+            detector.measure(&quiet, true);
+        }
+        let settled_floor = detector.noise_floor_lufs;
+
+        // A loud frame while not in `Silence` (e.g. `Pending` or `Speech`) must not move the
+        // floor, even though it's far louder than anything seen so far.
+        let loud = sine_tone(0.5, sample_rate, 240, &mut phase);
+        detector.measure(&loud, false);
+        assert_eq!(detector.noise_floor_lufs, settled_floor);
+
+        // Confirmed silence afterwards resumes tracking the quiet level rather than having
+        // jumped toward the loud excursion in between.
+        let quiet = sine_tone(0.001, sample_rate, 240, &mut phase);
+        detector.measure(&quiet, true);
+        assert!((detector.noise_floor_lufs - settled_floor).abs() < 2.0);
+    }
+
+    #[test]
+    fn loudness_onset_and_offset_margins_differ() {
+        let mut cfg = test_cfg();
+        cfg.mode = VadMode::Loudness;
+        cfg.loudness_offset_lu = 9.0;
+        cfg.loudness_release_lu = 6.0;
+        let detector = LoudnessDetector::new(cfg.sample_rate);
+        // A separate, lower release margin preserves the onset/offset hysteresis gap that
+        // `Energy` mode gets from `onset_threshold`/`offset_threshold` directly.
+        assert_eq!(detector.onset_threshold(&cfg) - detector.offset_threshold(&cfg), 3.0);
+        assert!(detector.onset_threshold(&cfg) > detector.offset_threshold(&cfg));
+    }
+
+    #[test]
+    fn applies_speech_pad_to_reported_boundaries() {
+        let mut cfg = test_cfg();
+        cfg.speech_pad_s = 0.02;
+        let mut vad = EnergyVad::new(cfg);
+        // Establish a nonzero timeline so the backdated start_time isn't clamped to 0.
+        vad.process(&silent_frame(50));
+        let events = vad.process(&loud_frame(30));
+        match events[0] {
+            VadEvent::SpeechStart { start_time } => assert!((start_time - 0.04).abs() < 1e-9),
+            other => panic!("expected SpeechStart, got {other:?}"),
+        }
+        let events = vad.process(&silent_frame(30));
+        match events[0] {
+            VadEvent::SpeechEnded { end_time } => assert!((end_time - 0.10).abs() < 1e-9),
+            other => panic!("expected SpeechEnded, got {other:?}"),
+        }
+    }
 }